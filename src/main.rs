@@ -1,5 +1,8 @@
 use {
-    clap::{crate_description, crate_name, crate_version, value_t, value_t_or_exit, App, Arg},
+    clap::{
+        crate_description, crate_name, crate_version, value_t, value_t_or_exit, App, Arg,
+        SubCommand,
+    },
     solana_clap_utils::{
         input_parsers::pubkey_of,
         input_validators::{
@@ -11,6 +14,7 @@ use {
     solana_client::nonblocking::rpc_client::RpcClient,
     solana_remote_wallet::remote_wallet::RemoteWalletManager,
     solana_sdk::{
+        clock::Slot,
         commitment_config::CommitmentConfig,
         signature::{Signature, Signer},
     },
@@ -23,6 +27,7 @@ struct Config {
     commitment_config: CommitmentConfig,
     default_signer: Box<dyn Signer>,
     json_rpc_url: String,
+    output_format: vv::OutputFormat,
     verbose: bool,
     websocket_url: String,
 }
@@ -75,6 +80,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .validator(is_valid_pubkey)
                 .value_name("ADDRESS")
                 .takes_value(true)
+                .global(true)
                 .help("Vote account address"),
         )
         .arg(
@@ -85,7 +91,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .takes_value(true)
                 .value_name("LIMIT")
                 .default_value("10")
-                .help("Number of transactions to process"),
+                .help(
+                    "Number of transactions to process, or the page size when \
+                     --until/--slots is given to page through an unbounded number of transactions",
+                ),
         )
         .arg(
             Arg::with_name("before")
@@ -96,6 +105,45 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .value_name("TRANSACTION_SIGNATURE")
                 .help("Start with the first vote older than this transaction signature"),
         )
+        .arg(
+            Arg::with_name("until")
+                .long("until")
+                .validator(is_parsable::<Signature>)
+                .takes_value(true)
+                .value_name("TRANSACTION_SIGNATURE")
+                .help("Stop at this transaction signature, paging as needed to reach it"),
+        )
+        .arg(
+            Arg::with_name("slots")
+                .long("slots")
+                .validator(is_parsable::<Slot>)
+                .takes_value(true)
+                .value_name("N")
+                .help("Keep paging until this many slots are covered, rather than a fixed transaction count"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .global(true)
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(&["json", "json-compact"])
+                .help("Return information in specified output format"),
+        )
+        .arg(
+            Arg::with_name("jobs")
+                .long("jobs")
+                .short("j")
+                .global(true)
+                .validator(is_parsable::<usize>)
+                .takes_value(true)
+                .value_name("JOBS")
+                .default_value("10")
+                .help("Maximum number of concurrent RPC requests when fetching transactions"),
+        )
+        .subcommand(
+            SubCommand::with_name("watch").about("Stream new votes as they land"),
+        )
         .get_matches();
 
     let mut wallet_manager: Option<Arc<RemoteWalletManager>> = None;
@@ -123,6 +171,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         );
 
         let websocket_url = solana_cli_config::Config::compute_websocket_url(&json_rpc_url);
+        let output_format = matches
+            .value_of("output")
+            .map(|output| output.parse().unwrap())
+            .unwrap_or_default();
         Config {
             commitment_config: CommitmentConfig::confirmed(),
             default_signer: default_signer
@@ -132,6 +184,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     exit(1);
                 }),
             json_rpc_url,
+            output_format,
             verbose: matches.is_present("verbose"),
             websocket_url,
         }
@@ -147,14 +200,42 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let vote_account_address = pubkey_of(&matches, "vote_account_address")
         .unwrap_or_else(|| config.default_signer.pubkey());
-    let limit = value_t_or_exit!(matches, "limit", usize);
-    let before = value_t!(matches, "before", Signature).ok();
-    vv::process_view_votes(&rpc_client, &vote_account_address, limit, before)
+    let jobs = value_t_or_exit!(matches, "jobs", usize);
+
+    if matches.subcommand_matches("watch").is_some() {
+        vv::process_watch_votes(
+            &rpc_client,
+            &config.websocket_url,
+            &vote_account_address,
+            config.output_format,
+            jobs,
+        )
         .await
         .unwrap_or_else(|err| {
             eprintln!("error: {}", err);
             exit(1);
         });
+    } else {
+        let limit = value_t_or_exit!(matches, "limit", usize);
+        let before = value_t!(matches, "before", Signature).ok();
+        let until = value_t!(matches, "until", Signature).ok();
+        let slots = value_t!(matches, "slots", Slot).ok();
+        vv::process_view_votes(
+            &rpc_client,
+            &vote_account_address,
+            limit,
+            before,
+            until,
+            slots,
+            config.output_format,
+            jobs,
+        )
+        .await
+        .unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            exit(1);
+        });
+    }
 
     Ok(())
 }
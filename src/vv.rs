@@ -1,42 +1,165 @@
 use {
+    futures_util::StreamExt,
     solana_client::{
         nonblocking::rpc_client::RpcClient, rpc_client::GetConfirmedSignaturesForAddress2Config,
+        rpc_config::RpcGetVoteAccountsConfig,
         rpc_response::RpcConfirmedTransactionStatusWithSignature,
     },
+    solana_pubsub_client::nonblocking::pubsub_client::PubsubClient,
     solana_sdk::{
-        clock::Slot, program_utils::limited_deserialize, pubkey::Pubkey, signature::Signature,
+        clock::{Epoch, Slot},
+        program_utils::limited_deserialize,
+        pubkey::Pubkey,
+        signature::Signature,
         transaction::SanitizedTransaction,
     },
     solana_transaction_status::UiTransactionEncoding,
-    solana_vote_program::vote_instruction::VoteInstruction,
-    solana_vote_program::vote_state::Vote,
+    solana_vote_program::{vote_instruction::VoteInstruction, vote_state::VoteStateUpdate},
     std::{
         cmp::Ordering,
         collections::{BTreeMap, HashMap},
         fmt,
+        str::FromStr,
     },
 };
 
-fn is_simple_vote_transaction(transaction: &SanitizedTransaction) -> Option<Vote> {
-    if transaction.message().instructions().len() == 1 {
-        let (program_pubkey, instruction) = transaction
-            .message()
-            .program_instructions_iter()
-            .next()
-            .unwrap();
-        if program_pubkey == &solana_vote_program::id() {
-            if let Ok(vote_instruction) = limited_deserialize::<VoteInstruction>(&instruction.data)
-            {
-                match vote_instruction {
-                    VoteInstruction::Vote(vote) | VoteInstruction::VoteSwitch(vote, _) => {
-                        return Some(vote)
-                    }
-                    _ => {}
-                }
-            }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Display,
+    Json,
+    JsonCompact,
+}
+
+impl Default for OutputFormat {
+    fn default() -> Self {
+        Self::Display
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "json-compact" => Ok(Self::JsonCompact),
+            _ => Err(format!("invalid output format: {}", s)),
+        }
+    }
+}
+
+#[derive(serde::Serialize, Debug)]
+struct VoteRecord {
+    signature: String,
+    success: bool,
+    voted_slots: Vec<Slot>,
+    landed_slot: Slot,
+    landing_latency: Slot,
+}
+
+#[derive(serde::Serialize, Debug)]
+struct SlotRange {
+    start: Slot,
+    end: Slot,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+struct VoteAccountStatus {
+    delinquent: bool,
+    root_slot: Slot,
+    last_vote_slot: Slot,
+    epoch_credits: Vec<(Epoch, u64, u64)>,
+}
+
+#[derive(serde::Serialize, Debug)]
+struct ViewVotesResult {
+    slot_range: SlotRange,
+    votes: Vec<VoteRecord>,
+    missed_slots: Vec<Slot>,
+    skipped_slots: Vec<Slot>,
+    confirmed_slot_count: usize,
+    missed_slot_count: usize,
+    failed_vote_count: usize,
+    vote_account_status: Option<VoteAccountStatus>,
+}
+
+async fn fetch_vote_account_status(
+    rpc_client: &RpcClient,
+    vote_account_address: &Pubkey,
+) -> Result<Option<VoteAccountStatus>, Box<dyn std::error::Error>> {
+    let vote_accounts = rpc_client
+        .get_vote_accounts_with_config(RpcGetVoteAccountsConfig {
+            vote_pubkey: Some(vote_account_address.to_string()),
+            ..RpcGetVoteAccountsConfig::default()
+        })
+        .await?;
+
+    let vote_pubkey = vote_account_address.to_string();
+    let delinquent = vote_accounts
+        .delinquent
+        .iter()
+        .any(|vote_account| vote_account.vote_pubkey == vote_pubkey);
+
+    let vote_account_info = vote_accounts
+        .current
+        .into_iter()
+        .chain(vote_accounts.delinquent)
+        .find(|vote_account| vote_account.vote_pubkey == vote_pubkey);
+
+    Ok(
+        vote_account_info.map(|vote_account_info| VoteAccountStatus {
+            delinquent,
+            root_slot: vote_account_info.root_slot,
+            last_vote_slot: vote_account_info.last_vote,
+            epoch_credits: vote_account_info.epoch_credits,
+        }),
+    )
+}
+
+// Mirrors the approach taken by `solana_vote_program::vote_parser::parse_vote_transaction`,
+// but returns just the normalized, sorted set of voted-on slots so callers don't need to
+// special-case the particular vote instruction variant that was used.
+fn parse_vote_transaction(transaction: &SanitizedTransaction) -> Option<Vec<Slot>> {
+    let (_program_pubkey, instruction) = transaction
+        .message()
+        .program_instructions_iter()
+        .find(|(program_pubkey, _instruction)| *program_pubkey == &solana_vote_program::id())?;
+
+    let vote_instruction = limited_deserialize::<VoteInstruction>(&instruction.data).ok()?;
+
+    let mut slots = match vote_instruction {
+        VoteInstruction::Vote(vote) | VoteInstruction::VoteSwitch(vote, _) => vote.slots,
+        VoteInstruction::UpdateVoteState(vote_state_update)
+        | VoteInstruction::UpdateVoteStateSwitch(vote_state_update, _) => vote_state_update
+            .lockouts
+            .iter()
+            .map(|lockout| lockout.slot())
+            .collect(),
+        VoteInstruction::CompactUpdateVoteState(compact_vote_state_update)
+        | VoteInstruction::CompactUpdateVoteStateSwitch(compact_vote_state_update, _) => {
+            // `CompactVoteStateUpdate` delta/offset-encodes lockouts relative to `root`;
+            // convert to `VoteStateUpdate` to get absolute slot numbers back.
+            let vote_state_update: VoteStateUpdate = compact_vote_state_update.into();
+            vote_state_update
+                .lockouts
+                .iter()
+                .map(|lockout| lockout.slot())
+                .collect()
         }
+        VoteInstruction::TowerSync(tower_sync)
+        | VoteInstruction::TowerSyncSwitch(tower_sync, _) => tower_sync
+            .lockouts
+            .iter()
+            .map(|lockout| lockout.slot())
+            .collect(),
+        _ => return None,
+    };
+
+    if slots.is_empty() {
+        return None;
     }
-    None
+    slots.sort_unstable();
+    Some(slots)
 }
 
 #[derive(Default, Debug, PartialEq, Eq, Clone)]
@@ -122,78 +245,114 @@ impl PartialOrd for TableEntry {
     }
 }
 
-pub async fn process_view_votes(
+// Up to `jobs` `get_transaction` requests run concurrently; re-sorted by landed slot after.
+async fn fetch_vote_metas(
     rpc_client: &RpcClient,
-    vote_account_address: &Pubkey,
-    limit: usize,
-    before: Option<Signature>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let signatures_for_address = rpc_client
-        .get_signatures_for_address_with_config(
-            vote_account_address,
-            GetConfirmedSignaturesForAddress2Config {
-                limit: Some(limit),
-                before,
-                ..GetConfirmedSignaturesForAddress2Config::default()
-            },
-        )
-        .await?;
-
-    println!("{} transaction to processs:", signatures_for_address.len(),);
-    if signatures_for_address.is_empty() {
-        return Ok(());
-    }
-
-    let mut vote_metas = vec![];
-    let mut slot_vote_count = HashMap::<Slot, usize>::default();
+    signatures_for_address: Vec<RpcConfirmedTransactionStatusWithSignature>,
+    output_format: OutputFormat,
+    jobs: usize,
+) -> Result<Vec<VoteMeta>, Box<dyn std::error::Error>> {
+    let mut vote_metas: Vec<VoteMeta> =
+        futures_util::stream::iter(signatures_for_address.into_iter().map(|entry| async move {
+            let RpcConfirmedTransactionStatusWithSignature {
+                signature,
+                slot: landed_slot,
+                err,
+                ..
+            } = entry;
+            let signature = signature.parse::<Signature>().unwrap();
+            if output_format == OutputFormat::Display {
+                println!("{}", signature);
+            }
+            let transaction = rpc_client
+                .get_transaction(&signature, UiTransactionEncoding::Base64)
+                .await?
+                .transaction
+                .transaction
+                .decode()
+                .expect("decode");
 
-    for RpcConfirmedTransactionStatusWithSignature {
-        signature,
-        slot: landed_slot,
-        err,
-        ..
-    } in signatures_for_address
-    {
-        let signature = signature.parse::<Signature>().unwrap();
-        println!("{}", signature);
-        let transaction = rpc_client
-            .get_transaction(&signature, UiTransactionEncoding::Base64)
-            .await?
-            .transaction
-            .transaction
-            .decode()
-            .expect("decode");
-
-        let transaction = SanitizedTransaction::try_from_legacy_transaction(transaction)?;
-
-        if let Some(vote) = is_simple_vote_transaction(&transaction) {
-            /*
-            println!(
-                "VOTE! {} slot {}: {:?}",
-                if err.is_none() { " OK " } else { "FAIL" },
-                slot,
-                vote
-            );
-            */
-            if !vote.slots.is_empty() {
-                let mut vote_slots = vote.slots.clone();
-                vote_slots.sort_unstable();
-
-                for slot in *vote_slots.first().unwrap()..=landed_slot + 1 {
-                    slot_vote_count
-                        .entry(slot)
-                        .and_modify(|e| *e += 1)
-                        .or_insert(1);
-                }
+            let transaction = SanitizedTransaction::try_from_legacy_transaction(transaction)?;
 
-                vote_metas.push(VoteMeta {
+            Ok::<_, Box<dyn std::error::Error>>(parse_vote_transaction(&transaction).map(
+                |vote_slots| VoteMeta {
                     signature,
                     success: err.is_none(),
                     landed_slot,
                     vote_slots,
-                });
+                },
+            ))
+        }))
+        .buffer_unordered(jobs.max(1))
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .flatten()
+        .collect();
+
+    vote_metas.sort_by(|a, b| b.landed_slot.cmp(&a.landed_slot));
+
+    Ok(vote_metas)
+}
+
+// `min_render_slot` lets `watch` skip rows it already printed; returns the slot past the end
+// of the table for the caller to pass back in next time.
+async fn render_vote_table(
+    rpc_client: &RpcClient,
+    mut vote_metas: Vec<VoteMeta>,
+    vote_account_status: Option<VoteAccountStatus>,
+    output_format: OutputFormat,
+    min_render_slot: Slot,
+) -> Result<Slot, Box<dyn std::error::Error>> {
+    if vote_metas.is_empty() {
+        match output_format {
+            OutputFormat::Display => {
+                println!("No votes found");
+                if let Some(vote_account_status) = &vote_account_status {
+                    println!(
+                        "Vote account: {} (root slot {}, last vote slot {})",
+                        if vote_account_status.delinquent {
+                            "DELINQUENT"
+                        } else {
+                            "current"
+                        },
+                        vote_account_status.root_slot,
+                        vote_account_status.last_vote_slot,
+                    );
+                }
+            }
+            OutputFormat::Json | OutputFormat::JsonCompact => {
+                let result = ViewVotesResult {
+                    slot_range: SlotRange { start: 0, end: 0 },
+                    votes: vec![],
+                    missed_slot_count: 0,
+                    missed_slots: vec![],
+                    skipped_slots: vec![],
+                    confirmed_slot_count: 0,
+                    failed_vote_count: 0,
+                    vote_account_status,
+                };
+                let serialized = if output_format == OutputFormat::JsonCompact {
+                    serde_json::to_string(&result)?
+                } else {
+                    serde_json::to_string_pretty(&result)?
+                };
+                println!("{}", serialized);
             }
         }
+        return Ok(min_render_slot);
+    }
+
+    let mut slot_vote_count = HashMap::<Slot, usize>::default();
+    for vote_meta in &vote_metas {
+        for slot in *vote_meta.vote_slots.first().unwrap()..=vote_meta.landed_slot + 1 {
+            slot_vote_count
+                .entry(slot)
+                .and_modify(|e| *e += 1)
+                .or_insert(1);
+        }
     }
 
     let slot_vote_max_depth = slot_vote_count.values().max().unwrap();
@@ -203,6 +362,7 @@ pub async fn process_view_votes(
 
     vote_metas.sort_by(|a, b| b.landed_slot.cmp(&a.landed_slot));
     let mut max_last_vote_slot = 0;
+    let mut vote_records = vec![];
     for vote_meta in vote_metas {
         let first_vote_slot = vote_meta.vote_slots[0];
         let last_vote_slot = *vote_meta.vote_slots.last().unwrap();
@@ -211,6 +371,14 @@ pub async fn process_view_votes(
             failed_vote_count += 1;
         }
 
+        vote_records.push(VoteRecord {
+            signature: vote_meta.signature.to_string(),
+            success: vote_meta.success,
+            voted_slots: vote_meta.vote_slots.clone(),
+            landed_slot: vote_meta.landed_slot,
+            landing_latency: vote_meta.landed_slot.saturating_sub(last_vote_slot),
+        });
+
         let mut depth = 0;
         loop {
             let mut occupied = false;
@@ -263,9 +431,12 @@ pub async fn process_view_votes(
     }
     let confirmed_slots = rpc_client.get_blocks(start_slot, Some(end_slot)).await?;
 
-    let mut miss_count = 0;
+    let mut missed_slots = vec![];
+    let mut skipped_slots = vec![];
 
-    println!();
+    if output_format == OutputFormat::Display && end_slot >= min_render_slot {
+        println!();
+    }
     for (slot, row_entries) in table {
         let confirmed = confirmed_slots.contains(&slot);
         let miss = slot < max_last_vote_slot
@@ -275,41 +446,273 @@ pub async fn process_view_votes(
                     .map(|entry| entry.kind == TableEntryKind::Vote && entry.vote_meta.success)
                     .unwrap_or(false)
             });
-        if confirmed && miss {
-            miss_count += 1
+        if !confirmed {
+            skipped_slots.push(slot);
+        } else if miss {
+            missed_slots.push(slot);
         }
-        println!(
-            "{0}{1:8}{0} {2}",
-            if confirmed {
-                if miss {
-                    " MISS "
+        if output_format == OutputFormat::Display && slot >= min_render_slot {
+            println!(
+                "{0}{1:8}{0} {2}",
+                if confirmed {
+                    if miss {
+                        " MISS "
+                    } else {
+                        "      "
+                    }
                 } else {
-                    "      "
+                    " SKIP "
+                },
+                slot,
+                row_entries
+                    .into_iter()
+                    .map(|entry| format!("{} | ", entry.unwrap_or_default()))
+                    .collect::<String>()
+            );
+        }
+    }
+
+    match output_format {
+        OutputFormat::Display => {
+            println!(
+                "\nSlot Range: {}..{}\n{} of {} confirmed",
+                start_slot,
+                end_slot,
+                confirmed_slots.len(),
+                end_slot - start_slot + 1
+            );
+            if !missed_slots.is_empty() {
+                println!("Missed slots: {}", missed_slots.len());
+            }
+            if failed_vote_count > 0 {
+                println!("Failed vote transactions: {}", failed_vote_count);
+            }
+            if let Some(vote_account_status) = &vote_account_status {
+                println!(
+                    "Vote account: {} (root slot {}, last vote slot {})",
+                    if vote_account_status.delinquent {
+                        "DELINQUENT"
+                    } else {
+                        "current"
+                    },
+                    vote_account_status.root_slot,
+                    vote_account_status.last_vote_slot,
+                );
+                if let Some((epoch, credits, prev_credits)) =
+                    vote_account_status.epoch_credits.last()
+                {
+                    // Current-epoch total, not scoped to the slot range shown above.
+                    println!(
+                        "Epoch {} total credits earned: {}",
+                        epoch,
+                        credits.saturating_sub(*prev_credits)
+                    );
                 }
+            }
+        }
+        OutputFormat::Json | OutputFormat::JsonCompact => {
+            let result = ViewVotesResult {
+                slot_range: SlotRange {
+                    start: start_slot,
+                    end: end_slot,
+                },
+                votes: vote_records,
+                missed_slot_count: missed_slots.len(),
+                missed_slots,
+                skipped_slots,
+                confirmed_slot_count: confirmed_slots.len(),
+                failed_vote_count,
+                vote_account_status,
+            };
+            let serialized = if output_format == OutputFormat::JsonCompact {
+                serde_json::to_string(&result)?
             } else {
-                " SKIP "
-            },
-            slot,
-            row_entries
-                .into_iter()
-                .map(|entry| format!("{} | ", entry.unwrap_or_default()))
-                .collect::<String>()
-        );
+                serde_json::to_string_pretty(&result)?
+            };
+            println!("{}", serialized);
+        }
     }
 
-    println!(
-        "\nSlot Range: {}..{}\n{} of {} confirmed",
-        start_slot,
-        end_slot,
-        confirmed_slots.len(),
-        end_slot - start_slot + 1
-    );
-    if miss_count > 0 {
-        println!("Missed slots: {}", miss_count);
+    Ok(end_slot + 1)
+}
+
+// Pages until the `until` signature or `slots` window is satisfied, or (with neither set)
+// a single page is returned.
+async fn fetch_signatures_for_address(
+    rpc_client: &RpcClient,
+    vote_account_address: &Pubkey,
+    limit: usize,
+    mut before: Option<Signature>,
+    until: Option<Signature>,
+    slots: Option<Slot>,
+) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>, Box<dyn std::error::Error>> {
+    let mut signatures_for_address = vec![];
+
+    loop {
+        let page = rpc_client
+            .get_signatures_for_address_with_config(
+                vote_account_address,
+                GetConfirmedSignaturesForAddress2Config {
+                    limit: Some(limit),
+                    before,
+                    until,
+                    ..GetConfirmedSignaturesForAddress2Config::default()
+                },
+            )
+            .await?;
+
+        if page.is_empty() {
+            break;
+        }
+
+        let oldest_slot = page.last().map(|entry| entry.slot);
+
+        before = page.last().and_then(|entry| entry.signature.parse().ok());
+        signatures_for_address.extend(page);
+
+        // `until` is excluded from returned pages by the RPC itself, so reaching it shows up
+        // as the next page coming back empty (handled above), not as a page containing it.
+
+        let slot_window_satisfied = slots
+            .zip(oldest_slot)
+            .map(|(slots, oldest_slot)| {
+                signatures_for_address[0].slot.saturating_sub(oldest_slot) >= slots
+            })
+            .unwrap_or(false);
+        if slot_window_satisfied {
+            break;
+        }
+
+        if until.is_none() && slots.is_none() {
+            break;
+        }
     }
-    if failed_vote_count > 0 {
-        println!("Failed vote transactions: {}", failed_vote_count);
+
+    Ok(signatures_for_address)
+}
+
+pub async fn process_view_votes(
+    rpc_client: &RpcClient,
+    vote_account_address: &Pubkey,
+    limit: usize,
+    before: Option<Signature>,
+    until: Option<Signature>,
+    slots: Option<Slot>,
+    output_format: OutputFormat,
+    jobs: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signatures_for_address = fetch_signatures_for_address(
+        rpc_client,
+        vote_account_address,
+        limit,
+        before,
+        until,
+        slots,
+    )
+    .await?;
+
+    if output_format == OutputFormat::Display {
+        println!("{} transaction to processs:", signatures_for_address.len(),);
+    }
+
+    let vote_metas =
+        fetch_vote_metas(rpc_client, signatures_for_address, output_format, jobs).await?;
+    let vote_account_status = fetch_vote_account_status(rpc_client, vote_account_address).await?;
+    render_vote_table(
+        rpc_client,
+        vote_metas,
+        vote_account_status,
+        output_format,
+        0,
+    )
+    .await
+    .map(|_end_slot| ())
+}
+
+// Bounds memory, the per-tick `get_blocks` range, and the printed table.
+const WATCH_HISTORY_LEN: usize = 64;
+// Batches between refreshes of the live vote account status.
+const WATCH_STATUS_REFRESH_EVERY: usize = 10;
+
+pub async fn process_watch_votes(
+    rpc_client: &RpcClient,
+    websocket_url: &str,
+    vote_account_address: &Pubkey,
+    output_format: OutputFormat,
+    jobs: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (mut slot_notifications, slot_unsubscribe) =
+        PubsubClient::slot_subscribe(websocket_url).await?;
+
+    // Seed on the most recent existing signature so the first poll only reports votes that
+    // land after this point, rather than replaying the account's pre-existing history.
+    let mut last_signature = rpc_client
+        .get_signatures_for_address_with_config(
+            vote_account_address,
+            GetConfirmedSignaturesForAddress2Config {
+                limit: Some(1),
+                ..GetConfirmedSignaturesForAddress2Config::default()
+            },
+        )
+        .await?
+        .first()
+        .map(|entry| entry.signature.parse::<Signature>())
+        .transpose()?;
+
+    let mut vote_metas = vec![];
+    let mut min_render_slot = 0;
+    let mut vote_account_status = None;
+    let mut batches_since_status_refresh = 0;
+
+    println!("Watching {} for new votes...", vote_account_address);
+
+    while slot_notifications.next().await.is_some() {
+        let signatures_for_address = rpc_client
+            .get_signatures_for_address_with_config(
+                vote_account_address,
+                GetConfirmedSignaturesForAddress2Config {
+                    until: last_signature,
+                    ..GetConfirmedSignaturesForAddress2Config::default()
+                },
+            )
+            .await?;
+
+        if signatures_for_address.is_empty() {
+            continue;
+        }
+        last_signature = Some(
+            signatures_for_address[0]
+                .signature
+                .parse::<Signature>()
+                .unwrap(),
+        );
+
+        let mut new_vote_metas =
+            fetch_vote_metas(rpc_client, signatures_for_address, output_format, jobs).await?;
+        if new_vote_metas.is_empty() {
+            continue;
+        }
+        vote_metas.append(&mut new_vote_metas);
+        vote_metas.sort_by(|a, b| b.landed_slot.cmp(&a.landed_slot));
+        vote_metas.truncate(WATCH_HISTORY_LEN);
+
+        if batches_since_status_refresh == 0 {
+            vote_account_status =
+                fetch_vote_account_status(rpc_client, vote_account_address).await?;
+        }
+        batches_since_status_refresh =
+            (batches_since_status_refresh + 1) % WATCH_STATUS_REFRESH_EVERY;
+
+        min_render_slot = render_vote_table(
+            rpc_client,
+            vote_metas.clone(),
+            vote_account_status.clone(),
+            output_format,
+            min_render_slot,
+        )
+        .await?;
     }
 
+    slot_unsubscribe().await;
     Ok(())
 }